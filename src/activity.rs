@@ -0,0 +1,122 @@
+//! Declarative Rich Presence.
+//!
+//! Instead of constructing an SDK `Activity` builder and calling
+//! `update_activity` by hand, users mutate the [`DiscordActivity`] resource
+//! like any other piece of game state; [`update_activity_system`] diffs it
+//! via Bevy change detection and pushes an update only on the frame it
+//! actually changed.
+
+use bevy_ecs::system::{NonSend, Res};
+use crossbeam_channel::Sender;
+
+use crate::events::DiscordEvent;
+use crate::*;
+
+/// Bevy resource mirroring the fields of the SDK's activity builder.
+///
+/// Leave a field at its default to omit it from the presence shown to
+/// other users.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiscordActivity {
+    pub state: String,
+    pub details: String,
+    pub start_timestamp: Option<i64>,
+    pub end_timestamp: Option<i64>,
+    pub large_image_key: String,
+    pub large_image_tooltip: String,
+    pub small_image_key: String,
+    pub small_image_tooltip: String,
+    pub party_size: Option<(u32, u32)>,
+    pub join_secret: String,
+    pub spectate_secret: String,
+}
+
+impl DiscordActivity {
+    fn to_sdk_activity(&self) -> Activity {
+        let mut activity = Activity::empty();
+        activity
+            .with_state(&self.state)
+            .with_details(&self.details)
+            .with_large_image_key(&self.large_image_key)
+            .with_large_image_tooltip(&self.large_image_tooltip)
+            .with_small_image_key(&self.small_image_key)
+            .with_small_image_tooltip(&self.small_image_tooltip)
+            .with_join_secret(&self.join_secret)
+            .with_spectate_secret(&self.spectate_secret);
+
+        if let Some(start) = self.start_timestamp {
+            activity.with_start_time(start);
+        }
+        if let Some(end) = self.end_timestamp {
+            activity.with_end_time(end);
+        }
+
+        if let Some((current, max)) = self.party_size {
+            activity.with_party_amount(current).with_party_capacity(max);
+        }
+
+        activity
+    }
+}
+
+/// Pushes [`DiscordActivity`] to Discord via `update_activity` whenever the
+/// resource changes, routing the completion callback into
+/// [`events::DiscordActivityUpdated`](crate::events::DiscordActivityUpdated).
+pub(crate) fn update_activity_system(
+    activity: Res<DiscordActivity>,
+    client: Option<NonSend<Client>>,
+    sender: Res<Sender<DiscordEvent>>,
+) {
+    if !activity.is_changed() {
+        return;
+    }
+
+    let client = match client {
+        Some(client) => client,
+        // No connected client to push the update to right now; it'll be
+        // dropped until the resource changes again.
+        None => return,
+    };
+
+    let sender = sender.clone();
+    client.update_activity(&activity.to_sdk_activity(), move |_discord, result| {
+        let _ = sender.send(DiscordEvent::ActivityUpdated(result));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carries_state_and_details_through() {
+        let activity = DiscordActivity {
+            state: "In a match".to_owned(),
+            details: "Ranked 3v3".to_owned(),
+            ..Default::default()
+        };
+
+        let sdk_activity = activity.to_sdk_activity();
+
+        assert_eq!(sdk_activity.state(), "In a match");
+        assert_eq!(sdk_activity.details(), "Ranked 3v3");
+    }
+
+    #[test]
+    fn carries_party_size_through() {
+        let activity = DiscordActivity { party_size: Some((2, 4)), ..Default::default() };
+
+        let sdk_activity = activity.to_sdk_activity();
+
+        assert_eq!(sdk_activity.party_amount(), 2);
+        assert_eq!(sdk_activity.party_capacity(), 4);
+    }
+
+    #[test]
+    fn omits_party_size_when_unset() {
+        let sdk_activity = DiscordActivity::default().to_sdk_activity();
+
+        assert_eq!(sdk_activity.party_amount(), 0);
+        assert_eq!(sdk_activity.party_capacity(), 0);
+    }
+}
@@ -0,0 +1,104 @@
+//! Async avatar loading, decoded into Bevy `Texture` assets.
+//!
+//! [`DiscordImages::fetch_avatar`] kicks off the SDK's `fetch_image` call;
+//! the raw RGBA bytes it reports come back through the event bridge (see
+//! [`crate::events`]) as [`DiscordAvatarFetched`], and
+//! [`decode_avatar_system`] turns those into a `Texture` asset, reporting
+//! the resulting handle via [`DiscordAvatarLoaded`]. This spares callers
+//! from ever touching the SDK's raw image buffers.
+
+use bevy_app::AppBuilder;
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::event::{EventReader, Events};
+use bevy_ecs::system::{Local, NonSend, Res, ResMut, SystemParam};
+use bevy_log::error;
+use bevy_render::texture::{Extent3d, Texture, TextureDimension, TextureFormat};
+use crossbeam_channel::Sender;
+
+use crate::events::DiscordEvent;
+use crate::*;
+
+/// Raw RGBA bytes reported by the SDK for a [`DiscordImages::fetch_avatar`]
+/// request, not yet decoded into a `Texture` asset.
+pub struct DiscordAvatarFetched {
+    pub user_id: UserID,
+    pub width: u32,
+    pub height: u32,
+    pub data: Result<Vec<u8>>,
+}
+
+/// Emitted once a fetched avatar has been decoded into a `Texture` asset.
+pub struct DiscordAvatarLoaded {
+    pub user_id: UserID,
+    pub handle: Handle<Texture>,
+}
+
+/// Requests avatars through the SDK's image fetch/read pair.
+#[derive(SystemParam)]
+pub struct DiscordImages<'a> {
+    client: NonSend<'a, Client>,
+    sender: Res<'a, Sender<DiscordEvent>>,
+}
+
+impl<'a> DiscordImages<'a> {
+    /// Requests `user_id`'s avatar at `size` pixels square. The raw result,
+    /// success or failure, is reported asynchronously via
+    /// [`DiscordAvatarFetched`]; a successful fetch is then reported via
+    /// [`DiscordAvatarLoaded`] once decoded.
+    pub fn fetch_avatar(&self, user_id: UserID, size: u32) {
+        let sender = self.sender.clone();
+        let handle = ImageHandle::from_user_id(user_id, size);
+        let lookup_handle = handle.clone();
+
+        self.client
+            .fetch_image(handle, FetchKind::UseCached, move |discord, result| {
+                let image = result.and_then(|()| discord.image(lookup_handle.clone()));
+                let data = match image {
+                    Ok(image) => DiscordEvent::AvatarFetched {
+                        user_id,
+                        width: image.width(),
+                        height: image.height(),
+                        data: Ok(image.data().to_vec()),
+                    },
+                    Err(err) => DiscordEvent::AvatarFetched {
+                        user_id,
+                        width: size,
+                        height: size,
+                        data: Err(err),
+                    },
+                };
+                let _ = sender.send(data);
+            });
+    }
+}
+
+/// Decodes [`DiscordAvatarFetched`] payloads into `Texture` assets and
+/// reports the resulting handles via [`DiscordAvatarLoaded`]. Failed
+/// fetches are logged and otherwise dropped.
+pub(crate) fn decode_avatar_system(
+    mut reader: Local<EventReader<DiscordAvatarFetched>>,
+    fetched: Res<Events<DiscordAvatarFetched>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut loaded: ResMut<Events<DiscordAvatarLoaded>>,
+) {
+    for event in reader.iter(&fetched) {
+        let data = match &event.data {
+            Ok(data) => data.clone(),
+            Err(err) => {
+                error!("Failed to fetch Discord avatar for user {}: {}", event.user_id, err);
+                continue;
+            }
+        };
+
+        let size = Extent3d::new(event.width, event.height, 1);
+        let texture = Texture::new(size, TextureDimension::D2, data, TextureFormat::Rgba8UnormSrgb);
+        let handle = textures.add(texture);
+        loaded.send(DiscordAvatarLoaded { user_id: event.user_id, handle });
+    }
+}
+
+/// Registers the `Events<T>` types this module produces on `app`.
+pub(crate) fn register_events(app: &mut AppBuilder) {
+    app.add_event::<DiscordAvatarFetched>()
+        .add_event::<DiscordAvatarLoaded>();
+}
@@ -0,0 +1,156 @@
+//! Resilient client lifecycle management.
+//!
+//! `Discord::new` fails whenever the Discord desktop client isn't running
+//! (surfacing as `Error::NotRunning`), and previously that meant the
+//! `Client` non-send resource was simply never inserted, with no way to
+//! recover short of restarting the game. This module retries on a
+//! configurable interval instead, and also demotes back to retrying if a
+//! runtime call to `run_callbacks` reports that the connection was lost.
+
+use std::time::{Duration, Instant};
+
+use bevy_app::AppBuilder;
+use bevy_ecs::event::Events;
+use bevy_ecs::world::World;
+use bevy_log::error;
+use crossbeam_channel::Sender;
+
+use crate::events::{DiscordEvent, EventBridge};
+use crate::*;
+
+/// Registers the `Events<T>` types this module produces on `app`.
+pub(crate) fn register_events(app: &mut AppBuilder) {
+    app.add_event::<DiscordConnected>()
+        .add_event::<DiscordDisconnected>();
+}
+
+/// Current state of the managed [`Client`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConnectionState {
+    Disconnected,
+    Connecting,
+    Connected,
+}
+
+/// Tracks the lifecycle of the Discord client so other systems can react to
+/// connection loss instead of panicking on a missing `NonSend<Client>`.
+pub struct DiscordConnection {
+    pub state: ConnectionState,
+    pub(crate) client_id: ClientID,
+    pub(crate) create_flags: CreateFlags,
+    pub(crate) retry_interval: Duration,
+    pub(crate) next_attempt_at: Instant,
+}
+
+impl DiscordConnection {
+    pub(crate) fn new(client_id: ClientID, create_flags: CreateFlags, retry_interval: Duration) -> Self {
+        Self {
+            state: ConnectionState::Disconnected,
+            client_id,
+            create_flags,
+            retry_interval,
+            next_attempt_at: Instant::now(),
+        }
+    }
+}
+
+/// Emitted whenever the client successfully connects, whether that's the
+/// first attempt or a reconnect after [`DiscordDisconnected`].
+pub struct DiscordConnected;
+
+/// Emitted when a connected client is lost because a runtime call reported
+/// an error. Failed connection attempts are logged but do not emit this
+/// event.
+pub struct DiscordDisconnected {
+    pub error: Error,
+}
+
+/// Runs `Client::run_callbacks` when a client is present, and demotes
+/// [`DiscordConnection`] back to [`ConnectionState::Disconnected`] if it
+/// reports an error. This replaces the non-resilient `run_discord_callbacks`
+/// that used to live in the crate root, so it needs `&mut World` in order
+/// to drop the non-send `Client` resource on failure.
+pub(crate) fn run_discord_callbacks(world: &mut World) {
+    let result = match world.get_non_send_resource_mut::<Client>() {
+        Some(mut client) => client.run_callbacks(),
+        None => return,
+    };
+
+    if let Err(error) = result {
+        error!("Lost connection to Discord: {}", error);
+        world.remove_non_send_resource::<Client>();
+
+        {
+            let mut connection = world.get_resource_mut::<DiscordConnection>().unwrap();
+            connection.state = ConnectionState::Disconnected;
+            connection.next_attempt_at = Instant::now() + connection.retry_interval;
+        }
+        world
+            .get_resource_mut::<Events<DiscordDisconnected>>()
+            .unwrap()
+            .send(DiscordDisconnected { error });
+    }
+}
+
+/// Attempts to (re)connect every `retry_interval`, installing a fresh
+/// [`EventBridge`] and inserting the [`Client`] non-send resource on
+/// success.
+pub(crate) fn reconnect_system(world: &mut World) {
+    let now = Instant::now();
+
+    {
+        let connection = world.get_resource::<DiscordConnection>().unwrap();
+        if connection.state == ConnectionState::Connected || connection.next_attempt_at > now {
+            return;
+        }
+    }
+
+    let (client_id, create_flags, retry_interval) = {
+        let connection = world.get_resource::<DiscordConnection>().unwrap();
+        (connection.client_id, connection.create_flags, connection.retry_interval)
+    };
+
+    {
+        let mut connection = world.get_resource_mut::<DiscordConnection>().unwrap();
+        connection.state = ConnectionState::Connecting;
+    }
+
+    match Client::with_create_flags(client_id, create_flags) {
+        Ok(mut client) => {
+            let sender = world.get_resource::<Sender<DiscordEvent>>().unwrap().clone();
+            *client.event_handler_mut() = Some(EventBridge::new(sender));
+            world.insert_non_send_resource(client);
+
+            let mut connection = world.get_resource_mut::<DiscordConnection>().unwrap();
+            connection.state = ConnectionState::Connected;
+            connection.next_attempt_at = now + retry_interval;
+
+            world
+                .get_resource_mut::<Events<DiscordConnected>>()
+                .unwrap()
+                .send(DiscordConnected);
+        }
+        Err(err) => {
+            error!(
+                "Failed to connect to Discord, retrying in {:?}: {}",
+                retry_interval, err
+            );
+            let mut connection = world.get_resource_mut::<DiscordConnection>().unwrap();
+            connection.state = ConnectionState::Disconnected;
+            connection.next_attempt_at = now + retry_interval;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_connection_starts_disconnected_and_due_immediately() {
+        let connection = DiscordConnection::new(1234, CreateFlags::Default, Duration::from_secs(5));
+
+        assert_eq!(connection.state, ConnectionState::Disconnected);
+        assert!(connection.next_attempt_at <= Instant::now());
+    }
+}
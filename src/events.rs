@@ -0,0 +1,304 @@
+//! Bridges callbacks emitted by the Discord Game SDK's `EventHandler` into
+//! strongly-typed Bevy `Events<T>`.
+//!
+//! The SDK invokes `EventHandler` methods synchronously from within
+//! `Discord::run_callbacks`, which means they fire while the `Discord`
+//! non-send resource is mutably borrowed. That makes it impossible to touch
+//! other Bevy resources (including `Events<T>`) from inside the handler, so
+//! instead the handler pushes every event onto a crossbeam queue and a
+//! separate system drains that queue into `Events<T>` once `run_callbacks`
+//! has returned.
+
+use bevy_app::AppBuilder;
+use bevy_ecs::event::Events;
+use bevy_ecs::system::{IntoSystem, Res, ResMut};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+use crate::*;
+
+/// A single notification forwarded out of [`EventBridge`].
+///
+/// This is an internal transport type; systems should read the
+/// per-variant `Events<T>` types below (e.g. [`DiscordActivityJoin`])
+/// instead of this enum.
+pub(crate) enum DiscordEvent {
+    ActivityJoin(String),
+    ActivityInvite { kind: Action, user: User, activity: Activity },
+    ActivitySpectate(String),
+    ActivityJoinRequest(User),
+    RelationshipUpdate(Relationship),
+    LobbyMessage { lobby_id: LobbyID, member_id: UserID, data: Vec<u8> },
+    ActivityUpdated(Result<()>),
+    LobbyCreated(Result<Lobby>),
+    LobbyConnected(Result<Lobby>),
+    LobbySearch(Result<Vec<Lobby>>),
+    MemberConnected { lobby_id: LobbyID, member_id: UserID },
+    MemberDisconnected { lobby_id: LobbyID, member_id: UserID },
+    AvatarFetched { user_id: UserID, width: u32, height: u32, data: Result<Vec<u8>> },
+}
+
+/// Emitted when the local user accepts an invite to join an [`Activity`].
+pub struct DiscordActivityJoin {
+    pub secret: String,
+}
+
+/// Emitted when the local user receives an invite to join or spectate an
+/// [`Activity`].
+pub struct DiscordActivityInvite {
+    pub kind: Action,
+    pub user: User,
+    pub activity: Activity,
+}
+
+/// Emitted when the local user accepts an invite to spectate an
+/// [`Activity`].
+pub struct DiscordActivitySpectate {
+    pub secret: String,
+}
+
+/// Emitted when another user asks to join the local user's [`Activity`].
+pub struct DiscordActivityJoinRequest {
+    pub user: User,
+}
+
+/// Emitted whenever the SDK's relationship cache is refreshed for a single
+/// [`Relationship`].
+pub struct DiscordRelationshipUpdate {
+    pub relationship: Relationship,
+}
+
+/// Emitted when a message is broadcast to a lobby the local user is
+/// connected to.
+pub struct DiscordLobbyMessage {
+    pub lobby_id: LobbyID,
+    pub member_id: UserID,
+    pub data: Vec<u8>,
+}
+
+/// Emitted once Discord has finished applying an activity update requested
+/// through [`crate::activity::DiscordActivity`].
+pub struct DiscordActivityUpdated {
+    pub result: Result<()>,
+}
+
+/// [`EventHandler`] implementation installed on the [`Discord`] client.
+///
+/// It does no work of its own beyond forwarding every callback it receives
+/// onto `sender`; see the module docs for why. Other systems (e.g. the
+/// activity and lobby update systems) share the same `Sender<DiscordEvent>`
+/// so that results of their own async SDK calls flow through the same
+/// drain system as native SDK callbacks.
+pub struct EventBridge {
+    sender: Sender<DiscordEvent>,
+}
+
+impl EventBridge {
+    pub(crate) fn new(sender: Sender<DiscordEvent>) -> Self {
+        Self { sender }
+    }
+}
+
+/// Creates the channel shared between [`EventBridge`] and [`drain_discord_events`].
+pub(crate) fn channel() -> (Sender<DiscordEvent>, Receiver<DiscordEvent>) {
+    unbounded()
+}
+
+impl EventHandler for EventBridge {
+    fn on_activity_join(&mut self, _discord: &Discord<'_, Self>, secret: &str) {
+        let _ = self.sender.send(DiscordEvent::ActivityJoin(secret.to_owned()));
+    }
+
+    fn on_activity_spectate(&mut self, _discord: &Discord<'_, Self>, secret: &str) {
+        let _ = self.sender.send(DiscordEvent::ActivitySpectate(secret.to_owned()));
+    }
+
+    fn on_activity_invite(
+        &mut self,
+        _discord: &Discord<'_, Self>,
+        kind: Action,
+        user: &User,
+        activity: &Activity,
+    ) {
+        let _ = self.sender.send(DiscordEvent::ActivityInvite {
+            kind,
+            user: user.clone(),
+            activity: activity.clone(),
+        });
+    }
+
+    fn on_activity_join_request(&mut self, _discord: &Discord<'_, Self>, user: &User) {
+        let _ = self
+            .sender
+            .send(DiscordEvent::ActivityJoinRequest(user.clone()));
+    }
+
+    fn on_relationship_update(&mut self, _discord: &Discord<'_, Self>, relationship: &Relationship) {
+        let _ = self
+            .sender
+            .send(DiscordEvent::RelationshipUpdate(relationship.clone()));
+    }
+
+    fn on_lobby_message(
+        &mut self,
+        _discord: &Discord<'_, Self>,
+        lobby_id: LobbyID,
+        member_id: UserID,
+        data: &[u8],
+    ) {
+        let _ = self.sender.send(DiscordEvent::LobbyMessage {
+            lobby_id,
+            member_id,
+            data: data.to_vec(),
+        });
+    }
+
+    fn on_member_connect(&mut self, _discord: &Discord<'_, Self>, lobby_id: LobbyID, member_id: UserID) {
+        let _ = self
+            .sender
+            .send(DiscordEvent::MemberConnected { lobby_id, member_id });
+    }
+
+    fn on_member_disconnect(&mut self, _discord: &Discord<'_, Self>, lobby_id: LobbyID, member_id: UserID) {
+        let _ = self
+            .sender
+            .send(DiscordEvent::MemberDisconnected { lobby_id, member_id });
+    }
+}
+
+/// Registers the `Events<T>` types this module produces on `app`.
+pub(crate) fn register_events(app: &mut AppBuilder) {
+    app.add_event::<DiscordActivityJoin>()
+        .add_event::<DiscordActivityInvite>()
+        .add_event::<DiscordActivitySpectate>()
+        .add_event::<DiscordActivityJoinRequest>()
+        .add_event::<DiscordRelationshipUpdate>()
+        .add_event::<DiscordLobbyMessage>()
+        .add_event::<DiscordActivityUpdated>();
+}
+
+/// Drains the queue fed by [`EventBridge`] into their respective
+/// `Events<T>`. Scheduled after `run_discord_callbacks` so that every event
+/// raised this frame is visible within the same frame to `EventReader<T>`
+/// systems that run after this one; it is not ordered against other
+/// systems in its own stage.
+pub(crate) fn drain_discord_events(
+    receiver: Res<Receiver<DiscordEvent>>,
+    mut activity_join: ResMut<Events<DiscordActivityJoin>>,
+    mut activity_invite: ResMut<Events<DiscordActivityInvite>>,
+    mut activity_spectate: ResMut<Events<DiscordActivitySpectate>>,
+    mut activity_join_request: ResMut<Events<DiscordActivityJoinRequest>>,
+    mut relationship_update: ResMut<Events<DiscordRelationshipUpdate>>,
+    mut lobby_message: ResMut<Events<DiscordLobbyMessage>>,
+    mut activity_updated: ResMut<Events<DiscordActivityUpdated>>,
+    mut lobby_created: ResMut<Events<crate::lobby::DiscordLobbyCreated>>,
+    mut lobby_connected: ResMut<Events<crate::lobby::DiscordLobbyConnected>>,
+    mut lobby_search: ResMut<Events<crate::lobby::DiscordLobbySearchResult>>,
+    mut member_connected: ResMut<Events<crate::lobby::DiscordMemberConnected>>,
+    mut member_disconnected: ResMut<Events<crate::lobby::DiscordMemberDisconnected>>,
+    mut avatar_fetched: ResMut<Events<crate::avatar::DiscordAvatarFetched>>,
+) {
+    for event in receiver.try_iter() {
+        match event {
+            DiscordEvent::ActivityJoin(secret) => {
+                activity_join.send(DiscordActivityJoin { secret })
+            }
+            DiscordEvent::ActivityInvite { kind, user, activity } => {
+                activity_invite.send(DiscordActivityInvite { kind, user, activity })
+            }
+            DiscordEvent::ActivitySpectate(secret) => {
+                activity_spectate.send(DiscordActivitySpectate { secret })
+            }
+            DiscordEvent::ActivityJoinRequest(user) => {
+                activity_join_request.send(DiscordActivityJoinRequest { user })
+            }
+            DiscordEvent::RelationshipUpdate(relationship) => {
+                relationship_update.send(DiscordRelationshipUpdate { relationship })
+            }
+            DiscordEvent::LobbyMessage { lobby_id, member_id, data } => {
+                lobby_message.send(DiscordLobbyMessage { lobby_id, member_id, data })
+            }
+            DiscordEvent::ActivityUpdated(result) => {
+                activity_updated.send(DiscordActivityUpdated { result })
+            }
+            DiscordEvent::LobbyCreated(result) => {
+                lobby_created.send(crate::lobby::DiscordLobbyCreated { result })
+            }
+            DiscordEvent::LobbyConnected(result) => {
+                lobby_connected.send(crate::lobby::DiscordLobbyConnected { result })
+            }
+            DiscordEvent::LobbySearch(result) => {
+                lobby_search.send(crate::lobby::DiscordLobbySearchResult { result })
+            }
+            DiscordEvent::MemberConnected { lobby_id, member_id } => member_connected.send(
+                crate::lobby::DiscordMemberConnected { lobby_id, member_id },
+            ),
+            DiscordEvent::MemberDisconnected { lobby_id, member_id } => member_disconnected.send(
+                crate::lobby::DiscordMemberDisconnected { lobby_id, member_id },
+            ),
+            DiscordEvent::AvatarFetched { user_id, width, height, data } => avatar_fetched.send(
+                crate::avatar::DiscordAvatarFetched { user_id, width, height, data },
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::schedule::{Schedule, SystemStage};
+    use bevy_ecs::world::World;
+
+    use super::*;
+
+    fn test_world_with(sender: Sender<DiscordEvent>, receiver: Receiver<DiscordEvent>) -> World {
+        let mut world = World::default();
+        world.insert_resource(sender);
+        world.insert_resource(receiver);
+        world.insert_resource(Events::<DiscordActivityJoin>::default());
+        world.insert_resource(Events::<DiscordActivityInvite>::default());
+        world.insert_resource(Events::<DiscordActivitySpectate>::default());
+        world.insert_resource(Events::<DiscordActivityJoinRequest>::default());
+        world.insert_resource(Events::<DiscordRelationshipUpdate>::default());
+        world.insert_resource(Events::<DiscordLobbyMessage>::default());
+        world.insert_resource(Events::<DiscordActivityUpdated>::default());
+        world.insert_resource(Events::<crate::lobby::DiscordLobbyCreated>::default());
+        world.insert_resource(Events::<crate::lobby::DiscordLobbyConnected>::default());
+        world.insert_resource(Events::<crate::lobby::DiscordLobbySearchResult>::default());
+        world.insert_resource(Events::<crate::lobby::DiscordMemberConnected>::default());
+        world.insert_resource(Events::<crate::lobby::DiscordMemberDisconnected>::default());
+        world.insert_resource(Events::<crate::avatar::DiscordAvatarFetched>::default());
+        world
+    }
+
+    #[test]
+    fn drains_a_native_callback_into_its_bevy_event() {
+        let (sender, receiver) = channel();
+        let mut world = test_world_with(sender.clone(), receiver);
+
+        sender.send(DiscordEvent::ActivityJoin("secret".to_owned())).unwrap();
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("drain", SystemStage::single(drain_discord_events.system()));
+        schedule.run(&mut world);
+
+        let events = world.get_resource::<Events<DiscordActivityJoin>>().unwrap();
+        let mut reader = events.get_reader();
+        let received: Vec<_> = reader.iter(events).map(|event| event.secret.clone()).collect();
+        assert_eq!(received, vec!["secret".to_owned()]);
+    }
+
+    #[test]
+    fn drains_a_self_reported_async_result_into_its_bevy_event() {
+        let (sender, receiver) = channel();
+        let mut world = test_world_with(sender.clone(), receiver);
+
+        sender.send(DiscordEvent::ActivityUpdated(Ok(()))).unwrap();
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("drain", SystemStage::single(drain_discord_events.system()));
+        schedule.run(&mut world);
+
+        let events = world.get_resource::<Events<DiscordActivityUpdated>>().unwrap();
+        let mut reader = events.get_reader();
+        assert_eq!(reader.iter(events).count(), 1);
+    }
+}
@@ -25,10 +25,12 @@
 //! use bevy::prelude::*;
 //! use bevy_discord_game_sdk::DiscordPlugin;
 //!
+//! const CLIENT_ID: i64 = 1234567890;
+//!
 //! fn main() {
 //!   App::build()
 //!       .add_plugins(DefaultPlugins)
-//!       .add_plugin(DiscordPlugin)
+//!       .add_plugin(DiscordPlugin::new(CLIENT_ID))
 //!       .run()
 //! }
 //! ```
@@ -40,13 +42,20 @@
 //! The plugin will automatically call `Discord::run_callbacks` on the Bevy
 //! main thread every frame, so there is no need to run it manually.
 //!
-//! **NOTE**: If the plugin fails to initialize (i.e. `Discord::new()` fails and
-//! returns an error, an error wil lbe logged (via `bevy_log`), but it will not
-//! panic. In this case, it may be necessary to use `Option<NonSend<Discord>>` instead.
+//! **NOTE**: If the Discord client isn't running when the plugin starts (or
+//! is closed and reopened while the game is running), the plugin will not
+//! panic or give up. It logs an error (via `bevy_log`) and keeps retrying on
+//! an interval (see [`DiscordPlugin::with_retry_interval`]); track
+//! [`DiscordConnection::state`] or read [`DiscordConnected`] /
+//! [`DiscordDisconnected`] to react to this in your own systems, and use
+//! `Option<NonSend<Client>>` for any system that may run before the first
+//! connection succeeds.
 //!
 //! ```rust
 //! use bevy_discord_game_sdk::{Client, FriendFlags};
 //!
+//! const CLIENT_ID: i64 = 1234567890;
+//!
 //! fn discord_system(client: NonSend<Client>) {
 //!   for friend in client.friends().get_friends(FriendFlags::IMMEDIATE) {
 //!     println!("Friend: {:?} - {}({:?})", friend.id(), friend.name(), friend.state());
@@ -56,31 +65,140 @@
 //! fn main() {
 //!   App::build()
 //!       .add_plugins(DefaultPlugins)
-//!       .add_plugin(DiscordPlugin)
+//!       .add_plugin(DiscordPlugin::new(CLIENT_ID))
 //!       .add_startup_system(discord_system.system())
 //!       .run()
 //! }
 //! ```
 
+mod activity;
+mod avatar;
+mod connection;
+mod events;
+mod lobby;
+
+use std::time::Duration;
+
 use bevy_app::{AppBuilder, Plugin};
-use bevy_ecs::system::{IntoSystem, NonSend};
-use bevy_log::error;
+use bevy_ecs::system::{IntoExclusiveSystem, IntoSystem};
 pub use discord_game_sdk::*;
 
-fn run_discord_callbacks(client: NonSend<Discord>) {
-    client.run_callbacks();
+pub use activity::DiscordActivity;
+pub use avatar::{DiscordAvatarFetched, DiscordAvatarLoaded, DiscordImages};
+pub use connection::{ConnectionState, DiscordConnected, DiscordConnection, DiscordDisconnected};
+pub use events::{
+    DiscordActivityInvite, DiscordActivityJoin, DiscordActivityJoinRequest, DiscordActivitySpectate,
+    DiscordActivityUpdated, DiscordLobbyMessage, DiscordRelationshipUpdate,
+};
+pub use lobby::{
+    DiscordLobbies, DiscordLobbyConnected, DiscordLobbyCreated, DiscordLobbyMembers,
+    DiscordLobbySearchResult, DiscordMemberConnected, DiscordMemberDisconnected,
+};
+
+/// Default interval between reconnection attempts; see
+/// [`DiscordPlugin::with_retry_interval`].
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The `Discord` client type used by this plugin, with [`events::EventBridge`]
+/// installed as its `EventHandler` so that SDK callbacks can be forwarded
+/// into Bevy `Events<T>`.
+pub type Client = Discord<'static, events::EventBridge>;
+
+/// Configures the [`DiscordPlugin`] before it's added to the app: the SDK
+/// `CreateFlags` used to open the client, how often to retry a lost
+/// connection, and which optional subsystems to install.
+pub struct DiscordPlugin {
+    client_id: ClientID,
+    create_flags: CreateFlags,
+    retry_interval: Duration,
+    enable_activity: bool,
+    enable_lobbies: bool,
+    enable_avatars: bool,
 }
 
-pub struct DiscordPlugin(ClientID);
+impl DiscordPlugin {
+    pub fn new(client_id: ClientID) -> Self {
+        Self {
+            client_id,
+            create_flags: CreateFlags::Default,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+            enable_activity: true,
+            enable_lobbies: true,
+            enable_avatars: true,
+        }
+    }
+
+    /// Overrides how often the plugin attempts to (re)connect to Discord
+    /// while [`DiscordConnection::state`] isn't
+    /// [`ConnectionState::Connected`].
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Overrides the `CreateFlags` passed to the SDK. Use
+    /// `CreateFlags::NoRequireDiscord` to let the game run without Discord
+    /// installed, relying on [`DiscordConnection`] to report that no client
+    /// is available rather than blocking startup on it.
+    pub fn with_create_flags(mut self, create_flags: CreateFlags) -> Self {
+        self.create_flags = create_flags;
+        self
+    }
+
+    /// Disables the [`DiscordActivity`] Rich Presence subsystem.
+    pub fn without_activity(mut self) -> Self {
+        self.enable_activity = false;
+        self
+    }
+
+    /// Disables the [`DiscordLobbies`] matchmaking subsystem.
+    pub fn without_lobbies(mut self) -> Self {
+        self.enable_lobbies = false;
+        self
+    }
+
+    /// Disables the [`DiscordImages`] avatar-loading subsystem.
+    pub fn without_avatars(mut self) -> Self {
+        self.enable_avatars = false;
+        self
+    }
+}
 
 impl Plugin for DiscordPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        match Discord::new(self.0) {
-            Err(err) => error!("Failed to initialize Discord client: {}", err),
-            Ok(client) => {
-                app.insert_non_send_resource(client)
-                    .add_system(run_discord_callbacks.system());
-            }
+        let (sender, receiver) = events::channel();
+
+        // All Events<T> types are registered unconditionally: `drain_discord_events`
+        // forwards into every one of them regardless of which optional
+        // subsystems below are enabled.
+        events::register_events(app);
+        connection::register_events(app);
+        lobby::register_events(app);
+        avatar::register_events(app);
+
+        app.insert_resource(DiscordConnection::new(
+            self.client_id,
+            self.create_flags,
+            self.retry_interval,
+        ))
+        .insert_resource(sender)
+        .insert_resource(receiver)
+        .add_system(connection::reconnect_system.exclusive_system())
+        .add_system(connection::run_discord_callbacks.exclusive_system())
+        .add_system(events::drain_discord_events.system());
+
+        if self.enable_activity {
+            app.init_resource::<DiscordActivity>()
+                .add_system(activity::update_activity_system.system());
+        }
+
+        if self.enable_lobbies {
+            app.init_resource::<DiscordLobbyMembers>()
+                .add_system(lobby::mirror_lobby_membership_system.system());
+        }
+
+        if self.enable_avatars {
+            app.add_system(avatar::decode_avatar_system.system());
         }
     }
 }
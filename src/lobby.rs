@@ -0,0 +1,190 @@
+//! Lobby and matchmaking as Bevy resources and events.
+//!
+//! [`DiscordLobbies`] wraps the SDK's lobby calls so systems can create,
+//! join, search, and message lobbies without holding onto the raw `Client`;
+//! results of the create, connect and search calls are delivered through the
+//! same event bridge as native callbacks (see [`crate::events`]).
+//! [`DiscordLobbyMembers`] records member connect/disconnect events per
+//! lobby, so systems can query membership without re-asking the SDK.
+
+use std::collections::{HashMap, HashSet};
+
+use bevy_app::AppBuilder;
+use bevy_ecs::event::{EventReader, Events};
+use bevy_ecs::system::{Local, NonSend, Res, ResMut, SystemParam};
+use crossbeam_channel::Sender;
+
+use crate::events::DiscordEvent;
+use crate::*;
+
+/// Tracks the members of each lobby as reported by member connect/disconnect
+/// events, kept up to date by [`mirror_lobby_membership_system`]. Members
+/// already present when the local user creates or joins a lobby are not
+/// recorded, and entries are not cleared when the local user leaves a lobby.
+#[derive(Default)]
+pub struct DiscordLobbyMembers {
+    members: HashMap<LobbyID, HashSet<UserID>>,
+}
+
+impl DiscordLobbyMembers {
+    /// The members of `lobby_id` the local user currently knows about.
+    pub fn members(&self, lobby_id: LobbyID) -> impl Iterator<Item = UserID> + '_ {
+        self.members.get(&lobby_id).into_iter().flatten().copied()
+    }
+
+    fn connect(&mut self, lobby_id: LobbyID, member_id: UserID) {
+        self.members.entry(lobby_id).or_default().insert(member_id);
+    }
+
+    fn disconnect(&mut self, lobby_id: LobbyID, member_id: UserID) {
+        if let Some(members) = self.members.get_mut(&lobby_id) {
+            members.remove(&member_id);
+        }
+    }
+}
+
+/// Emitted once a lobby created via [`DiscordLobbies::create_lobby`] is
+/// ready, or the attempt failed.
+pub struct DiscordLobbyCreated {
+    pub result: Result<Lobby>,
+}
+
+/// Emitted once a lobby joined via [`DiscordLobbies::connect_lobby`] is
+/// ready, or the attempt failed.
+pub struct DiscordLobbyConnected {
+    pub result: Result<Lobby>,
+}
+
+/// Emitted once a [`DiscordLobbies::search_lobbies`] query completes.
+pub struct DiscordLobbySearchResult {
+    pub result: Result<Vec<Lobby>>,
+}
+
+/// Emitted when a member joins a lobby the local user is connected to.
+pub struct DiscordMemberConnected {
+    pub lobby_id: LobbyID,
+    pub member_id: UserID,
+}
+
+/// Emitted when a member leaves a lobby the local user is connected to.
+pub struct DiscordMemberDisconnected {
+    pub lobby_id: LobbyID,
+    pub member_id: UserID,
+}
+
+/// A `SystemParam` bundling the pieces needed to drive the SDK's lobby
+/// calls from ordinary systems. Every call here is fire-and-forget. For
+/// `create_lobby`, `connect_lobby` and `search_lobbies`, read the
+/// corresponding `Events<T>` (e.g. [`DiscordLobbyCreated`]) for the result,
+/// the same way [`crate::activity::DiscordActivity`] reports back through
+/// [`crate::DiscordActivityUpdated`]. The results of
+/// `update_member_metadata` and `send_lobby_message` are discarded.
+#[derive(SystemParam)]
+pub struct DiscordLobbies<'a> {
+    client: NonSend<'a, Client>,
+    sender: Res<'a, Sender<DiscordEvent>>,
+}
+
+impl<'a> DiscordLobbies<'a> {
+    pub fn create_lobby(&self, transaction: &LobbyTransaction) {
+        let sender = self.sender.clone();
+        self.client.create_lobby(transaction, move |_discord, result| {
+            let _ = sender.send(DiscordEvent::LobbyCreated(result.map(Lobby::clone)));
+        });
+    }
+
+    pub fn connect_lobby(&self, lobby_id: LobbyID, secret: &str) {
+        let sender = self.sender.clone();
+        self.client
+            .connect_lobby(lobby_id, secret, move |_discord, result| {
+                let _ = sender.send(DiscordEvent::LobbyConnected(result.map(Lobby::clone)));
+            });
+    }
+
+    pub fn search_lobbies(&self, search: &SearchQuery) {
+        let sender = self.sender.clone();
+        self.client.lobby_search(search, move |discord, result| {
+            let result = result.and_then(|()| {
+                discord
+                    .iter_lobbies()
+                    .map(|id| id.and_then(|id| discord.lobby(id)))
+                    .collect::<Result<Vec<_>>>()
+            });
+            let _ = sender.send(DiscordEvent::LobbySearch(result));
+        });
+    }
+
+    pub fn update_member_metadata(&self, lobby_id: LobbyID, member_id: UserID, key: &str, value: &str) {
+        let mut transaction = LobbyMemberTransaction::new();
+        transaction.add_metadata(key.to_owned(), value.to_owned());
+        self.client
+            .update_member(lobby_id, member_id, &transaction, |_discord, _result| {});
+    }
+
+    pub fn send_lobby_message(&self, lobby_id: LobbyID, data: &[u8]) {
+        self.client
+            .send_lobby_message(lobby_id, data, |_discord, _result| {});
+    }
+}
+
+/// Registers the `Events<T>` types this module produces on `app`.
+pub(crate) fn register_events(app: &mut AppBuilder) {
+    app.add_event::<DiscordLobbyCreated>()
+        .add_event::<DiscordLobbyConnected>()
+        .add_event::<DiscordLobbySearchResult>()
+        .add_event::<DiscordMemberConnected>()
+        .add_event::<DiscordMemberDisconnected>();
+}
+
+/// Keeps [`DiscordLobbyMembers`] in sync with connect/disconnect events.
+pub(crate) fn mirror_lobby_membership_system(
+    mut members: ResMut<DiscordLobbyMembers>,
+    mut connected_reader: Local<EventReader<DiscordMemberConnected>>,
+    connected_events: Res<Events<DiscordMemberConnected>>,
+    mut disconnected_reader: Local<EventReader<DiscordMemberDisconnected>>,
+    disconnected_events: Res<Events<DiscordMemberDisconnected>>,
+) {
+    for event in connected_reader.iter(&connected_events) {
+        members.connect(event.lobby_id, event.member_id);
+    }
+    for event in disconnected_reader.iter(&disconnected_events) {
+        members.disconnect(event.lobby_id, event.member_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connect_adds_member_to_lobby() {
+        let mut members = DiscordLobbyMembers::default();
+        members.connect(1, 100);
+
+        assert_eq!(members.members(1).collect::<Vec<_>>(), vec![100]);
+    }
+
+    #[test]
+    fn disconnect_removes_member_from_lobby() {
+        let mut members = DiscordLobbyMembers::default();
+        members.connect(1, 100);
+        members.connect(1, 200);
+        members.disconnect(1, 100);
+
+        assert_eq!(members.members(1).collect::<Vec<_>>(), vec![200]);
+    }
+
+    #[test]
+    fn disconnect_from_unknown_lobby_is_a_noop() {
+        let mut members = DiscordLobbyMembers::default();
+        members.disconnect(1, 100);
+
+        assert_eq!(members.members(1).count(), 0);
+    }
+
+    #[test]
+    fn members_of_unknown_lobby_is_empty() {
+        let members = DiscordLobbyMembers::default();
+        assert_eq!(members.members(1).count(), 0);
+    }
+}